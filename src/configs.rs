@@ -18,19 +18,46 @@ pub(crate) struct Opts {
     pub telegram_token: Option<String>,
     #[clap(long)]
     pub chat_id: Vec<String>,
+    /// Slack incoming webhook URL to post alerts to
+    #[clap(long)]
+    pub slack_webhook_url: Option<String>,
+    /// Generic JSON-POST webhook URL to send alerts to
+    #[clap(long)]
+    pub webhook_url: Option<String>,
     #[clap(long, default_value = "10")]
     pub stats_interval_sec: u64,
+    /// Seconds without a new block before the stream is considered stalled and restarted
+    #[clap(long, default_value = "60")]
+    pub stall_timeout_sec: u64,
+    /// Alert when the block processing speed drops to or below this many blocks per second
+    #[clap(long, default_value = "0.0")]
+    pub min_bps: f64,
+    /// Alert when the wall-clock lag behind the processed block's timestamp exceeds this many seconds
+    #[clap(long, default_value = "120")]
+    pub max_lag_seconds: f64,
+    /// Minimum number of seconds between repeated alerts for a condition that stays unresolved
+    #[clap(long, default_value = "300")]
+    pub alert_repeat_sec: u64,
+    /// NATS server URL to publish per-block summaries to, e.g. nats://localhost:4222
+    #[clap(long)]
+    pub nats_url: Option<String>,
+    /// JetStream subject to publish per-block summaries to
+    #[clap(long, default_value = "pulse.blocks")]
+    pub nats_subject: String,
+    /// OTLP collector endpoint to export traces and the pulse_* metrics to, e.g. http://localhost:4317
+    #[clap(long)]
+    pub otlp_endpoint: Option<String>,
     #[clap(subcommand)]
     pub chain_id: ChainId,
 }
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 pub(crate) enum ChainId {
     Mainnet(RunArgs),
     Testnet(RunArgs),
 }
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 pub(crate) struct RunArgs {
     /// Block heigh to start watching from
     #[clap(short, long)]
@@ -46,6 +73,19 @@ impl Opts {
     }
 }
 
+impl ChainId {
+    /// Builds a `LakeConfig` for this chain, overriding the start block height so the
+    /// stream supervisor can resume from the last processed block after a reconnect.
+    pub fn with_start_block_height(&self, block_height: u64) -> near_lake_framework::LakeConfig {
+        let mut chain = self.clone();
+        match &mut chain {
+            ChainId::Mainnet(args) => args.block_height = block_height,
+            ChainId::Testnet(args) => args.block_height = block_height,
+        }
+        chain.into()
+    }
+}
+
 impl From<ChainId> for near_lake_framework::LakeConfig {
     fn from(chain: ChainId) -> near_lake_framework::LakeConfig {
         let config_builder = near_lake_framework::LakeConfigBuilder::default();
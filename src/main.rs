@@ -1,23 +1,23 @@
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use actix_web::{get, App, HttpServer, Responder};
 use clap::Parser;
 use futures::StreamExt;
 use lazy_static::lazy_static;
-use prometheus::{Encoder, Gauge, IntCounter, IntGauge};
-use teloxide::{
-    payloads::SendMessageSetters,
-    requests::{Request, Requester},
-    types::ParseMode,
-    Bot,
-};
-use tokio::sync::Mutex;
-use tracing_subscriber::EnvFilter;
-
-use configs::Opts;
-use near_lake_framework::LakeConfig;
+use prometheus::{Encoder, Gauge, Histogram, HistogramOpts, IntCounter, IntGauge};
+use teloxide::Bot;
+use tokio::sync::{mpsc, Mutex, Semaphore};
+use tracing::Instrument;
+
+use configs::{ChainId, Opts};
+use nats_sink::BlockSummary;
+use notifiers::{AlertCondition, Notifier, SlackNotifier, TelegramNotifier, WebhookNotifier};
 
 mod configs;
+mod nats_sink;
+mod notifiers;
+mod telemetry;
 
 lazy_static! {
     static ref LATEST_BLOCK_HEIGHT: IntGauge =
@@ -25,12 +25,54 @@ lazy_static! {
     static ref BLOCKS_INDEXED: IntCounter =
         IntCounter::new("pulse_blocks_indexed", "Number of indexed blocks").unwrap();
     static ref BPS: Gauge = Gauge::new("pulse_bps", "Blocks per second").unwrap();
+    static ref BLOCK_PROCESS_SECONDS: Histogram = Histogram::with_opts(
+        HistogramOpts::new(
+            "pulse_block_process_seconds",
+            "Wall-clock time spent inside handle_streamer_message"
+        )
+        .buckets(vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 30.0, 60.0])
+    )
+    .unwrap();
+    static ref BLOCK_LAG_SECONDS: Histogram = Histogram::with_opts(
+        HistogramOpts::new(
+            "pulse_block_lag_seconds",
+            "Difference between wall-clock time and the processed block's timestamp"
+        )
+        .buckets(vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 30.0, 60.0])
+    )
+    .unwrap();
+    static ref STREAM_RECONNECTS: IntCounter = IntCounter::new(
+        "pulse_stream_reconnects",
+        "Number of times the Lake stream has been torn down and rebuilt"
+    )
+    .unwrap();
+    static ref STREAM_CONNECTED: IntGauge = IntGauge::new(
+        "pulse_stream_connected",
+        "Whether the Lake stream is currently connected (1) or being rebuilt (0)"
+    )
+    .unwrap();
+    static ref NATS_PUBLISHED: IntCounter = IntCounter::new(
+        "pulse_nats_published",
+        "Number of block summaries published to NATS"
+    )
+    .unwrap();
+    static ref NATS_PUBLISH_ERRORS: IntCounter = IntCounter::new(
+        "pulse_nats_publish_errors",
+        "Number of failed NATS publish attempts"
+    )
+    .unwrap();
+    static ref CURRENT_LAG_SECONDS: Gauge = Gauge::new(
+        "pulse_current_lag_seconds",
+        "Wall-clock lag behind the most recently processed block's timestamp"
+    )
+    .unwrap();
 }
 
 #[derive(Debug, Clone)]
 struct Stats {
     pub blocks_processed_count: u64,
     pub last_processed_block_height: u64,
+    pub last_block_timestamp_secs: f64,
     pub bps: f64,
 }
 
@@ -39,6 +81,7 @@ impl Stats {
         Self {
             blocks_processed_count: 0,
             last_processed_block_height: 0,
+            last_block_timestamp_secs: 0.0,
             bps: 0.0,
         }
     }
@@ -46,23 +89,33 @@ impl Stats {
 
 #[derive(Debug, Clone, Copy)]
 enum State {
-    Alerting,
+    Alerting(AlertCondition),
     Operating,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), tokio::io::Error> {
-    init_tracing();
-
     let opts: Opts = Opts::parse();
+    telemetry::init_tracing(opts.otlp_endpoint.as_deref());
+    if let Some(otlp_endpoint) = &opts.otlp_endpoint {
+        telemetry::bridge_prometheus_metrics(otlp_endpoint);
+    }
+
     let telegram_token = opts.telegram_token.clone();
     let chat_ids = opts.chat_id.clone();
+    let slack_webhook_url = opts.slack_webhook_url.clone();
+    let webhook_url = opts.webhook_url.clone();
     let http_port = opts.http_port;
     let stats_interval_sec = opts.stats_interval_sec;
+    let stall_timeout_sec = opts.stall_timeout_sec;
+    let min_bps = opts.min_bps;
+    let max_lag_seconds = opts.max_lag_seconds;
+    let alert_repeat_sec = opts.alert_repeat_sec;
+    let nats_url = opts.nats_url.clone();
+    let nats_subject = opts.nats_subject.clone();
 
     let config_string = format!("Chain_id: {}", opts.chain_id());
-    let config: LakeConfig = opts.chain_id.into();
-    let (_, stream) = near_lake_framework::streamer(config);
+    let chain_id = opts.chain_id;
 
     // Register custom metrics to a custom registry.
     prometheus::default_registry()
@@ -74,29 +127,68 @@ async fn main() -> Result<(), tokio::io::Error> {
     prometheus::default_registry()
         .register(Box::new(BPS.clone()))
         .unwrap();
+    prometheus::default_registry()
+        .register(Box::new(BLOCK_PROCESS_SECONDS.clone()))
+        .unwrap();
+    prometheus::default_registry()
+        .register(Box::new(BLOCK_LAG_SECONDS.clone()))
+        .unwrap();
+    prometheus::default_registry()
+        .register(Box::new(STREAM_RECONNECTS.clone()))
+        .unwrap();
+    prometheus::default_registry()
+        .register(Box::new(STREAM_CONNECTED.clone()))
+        .unwrap();
+    prometheus::default_registry()
+        .register(Box::new(NATS_PUBLISHED.clone()))
+        .unwrap();
+    prometheus::default_registry()
+        .register(Box::new(NATS_PUBLISH_ERRORS.clone()))
+        .unwrap();
+    prometheus::default_registry()
+        .register(Box::new(CURRENT_LAG_SECONDS.clone()))
+        .unwrap();
+
+    let nats_sender = match nats_url {
+        Some(nats_url) => nats_sink::spawn_nats_sink(nats_url, nats_subject).await,
+        None => None,
+    };
 
     let stats: Arc<Mutex<Stats>> = Arc::new(Mutex::new(Stats::new()));
+
+    let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
     if let Some(token) = telegram_token {
         if !chat_ids.is_empty() {
-            let bot = Bot::new(token);
-
-            tokio::spawn(stats_watcher(
-                Arc::clone(&stats),
-                bot,
-                config_string,
-                chat_ids,
-                stats_interval_sec,
-            ));
+            notifiers.push(Box::new(TelegramNotifier::new(Bot::new(token), chat_ids)));
         }
     }
+    if let Some(url) = slack_webhook_url {
+        notifiers.push(Box::new(SlackNotifier::new(url)));
+    }
+    if let Some(url) = webhook_url {
+        notifiers.push(Box::new(WebhookNotifier::new(url)));
+    }
 
-    tokio::spawn(async move {
-        let mut handlers = tokio_stream::wrappers::ReceiverStream::new(stream)
-            .map(|streamer_message| handle_streamer_message(streamer_message, Arc::clone(&stats)))
-            .buffer_unordered(1usize);
+    if !notifiers.is_empty() {
+        tokio::spawn(stats_watcher(
+            Arc::clone(&stats),
+            notifiers,
+            config_string,
+            stats_interval_sec,
+            min_bps,
+            max_lag_seconds,
+            alert_repeat_sec,
+        ));
+    }
 
-        while let Some(_handle_message) = handlers.next().await {}
-    });
+    tokio::spawn(run_stream_supervisor(
+        chain_id,
+        stall_timeout_sec,
+        Arc::clone(&stats),
+        nats_sender,
+    ));
+
+    tokio::spawn(run_lag_gauge_ticker(Arc::clone(&stats), stats_interval_sec));
 
     HttpServer::new(|| App::new().service(metrics))
         .bind(("0.0.0.0", http_port))?
@@ -107,21 +199,162 @@ async fn main() -> Result<(), tokio::io::Error> {
     Ok(())
 }
 
+/// Keeps the Lake stream alive: rebuilds it whenever the receiver closes or goes
+/// `stall_timeout_sec` without yielding a new block, resuming from the last
+/// processed block height instead of starting over.
+///
+/// The stall timeout wraps the raw stream's `.next()` only, not the handling of
+/// the block it yields: `handle_streamer_message` (including its NATS publish,
+/// which can legitimately block on channel backpressure) runs in a spawned task
+/// instead, so a slow-but-healthy downstream doesn't get mistaken for a dead
+/// Lake connection. A semaphore still caps handling at one block at a time,
+/// matching the previous `buffer_unordered(1)` ordering guarantee.
+async fn run_stream_supervisor(
+    chain_id: ChainId,
+    stall_timeout_sec: u64,
+    stats: Arc<Mutex<Stats>>,
+    nats_sender: Option<mpsc::Sender<BlockSummary>>,
+) {
+    loop {
+        let last_processed_block_height = stats.lock().await.last_processed_block_height;
+        let config = if last_processed_block_height > 0 {
+            chain_id.with_start_block_height(last_processed_block_height + 1)
+        } else {
+            chain_id.clone().into()
+        };
+
+        STREAM_CONNECTED.set(1);
+        let (_, stream) = near_lake_framework::streamer(config);
+        let mut messages = tokio_stream::wrappers::ReceiverStream::new(stream);
+        let handling_permit = Arc::new(Semaphore::new(1));
+
+        loop {
+            match tokio::time::timeout(Duration::from_secs(stall_timeout_sec), messages.next())
+                .await
+            {
+                Ok(Some(streamer_message)) => {
+                    let span = tracing::info_span!(
+                        "block",
+                        height = streamer_message.block.header.height
+                    );
+                    let stats = Arc::clone(&stats);
+                    let nats_sender = nats_sender.clone();
+                    // Taken here rather than inside the task, so blocks are handled
+                    // in the order the stream yields them.
+                    let permit = Arc::clone(&handling_permit)
+                        .acquire_owned()
+                        .await
+                        .expect("handling semaphore was closed");
+                    tokio::spawn(
+                        async move {
+                            let _permit = permit;
+                            handle_streamer_message(streamer_message, stats, nats_sender).await;
+                        }
+                        .instrument(span),
+                    );
+                }
+                Ok(None) => {
+                    eprintln!("Lake stream ended, reconnecting...");
+                    break;
+                }
+                Err(_) => {
+                    eprintln!(
+                        "No new block in {}s, tearing down the stream and reconnecting...",
+                        stall_timeout_sec
+                    );
+                    break;
+                }
+            }
+        }
+
+        STREAM_CONNECTED.set(0);
+        STREAM_RECONNECTS.inc();
+    }
+}
+
+/// Keeps `pulse_current_lag_seconds` growing against wall-clock time during a
+/// full stall, instead of freezing at the lag observed when the last block was
+/// processed: `handle_streamer_message` only runs when a block actually
+/// arrives, so without this ticker the gauge (and the `HighLag` alert that
+/// reads it) wouldn't notice the chain going quiet until a block shows up again.
+async fn run_lag_gauge_ticker(stats: Arc<Mutex<Stats>>, interval_secs: u64) {
+    loop {
+        tokio::time::sleep(Duration::from_secs(interval_secs)).await;
+
+        let last_block_timestamp_secs = stats.lock().await.last_block_timestamp_secs;
+        if last_block_timestamp_secs <= 0.0 {
+            continue;
+        }
+
+        let now_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs_f64();
+        CURRENT_LAG_SECONDS.set((now_secs - last_block_timestamp_secs).max(0.0));
+    }
+}
+
 async fn handle_streamer_message(
     streamer_message: near_lake_framework::near_indexer_primitives::StreamerMessage,
     stats: Arc<Mutex<Stats>>,
+    nats_sender: Option<mpsc::Sender<BlockSummary>>,
 ) {
+    let started_at = Instant::now();
+
     BLOCKS_INDEXED.inc();
     LATEST_BLOCK_HEIGHT.set(streamer_message.block.header.height.try_into().unwrap());
     let mut stats_lock = stats.lock().await;
     BPS.set(stats_lock.bps);
     stats_lock.blocks_processed_count += 1;
     stats_lock.last_processed_block_height = streamer_message.block.header.height;
+    let block_timestamp_secs = streamer_message.block.header.timestamp as f64 / 1e9;
+    stats_lock.last_block_timestamp_secs = block_timestamp_secs;
     drop(stats_lock);
-    eprintln!(
-        "{} / shards {}",
-        streamer_message.block.header.height,
-        streamer_message.shards.len()
+
+    let now_secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs_f64();
+    let lag_seconds = (now_secs - block_timestamp_secs).max(0.0);
+    BLOCK_LAG_SECONDS.observe(lag_seconds);
+    CURRENT_LAG_SECONDS.set(lag_seconds);
+
+    if let Some(sender) = nats_sender {
+        let (chunks, transactions, receipts) = streamer_message.shards.iter().fold(
+            (0usize, 0usize, 0usize),
+            |(chunks, transactions, receipts), shard| match &shard.chunk {
+                Some(chunk) => (
+                    chunks + 1,
+                    transactions + chunk.transactions.len(),
+                    receipts + chunk.receipts.len(),
+                ),
+                None => (chunks, transactions, receipts),
+            },
+        );
+
+        let summary = BlockSummary {
+            height: streamer_message.block.header.height,
+            shards: streamer_message.shards.len(),
+            chunks,
+            transactions,
+            receipts,
+            timestamp: streamer_message.block.header.timestamp,
+        };
+
+        if sender.send(summary).await.is_err() {
+            tracing::warn!("NATS sink task is gone, dropping block summary");
+            NATS_PUBLISH_ERRORS.inc();
+        }
+    }
+
+    let process_seconds = started_at.elapsed().as_secs_f64();
+    BLOCK_PROCESS_SECONDS.observe(process_seconds);
+
+    tracing::info!(
+        shards = streamer_message.shards.len(),
+        lag_seconds,
+        process_seconds,
+        "block processed"
     );
 }
 
@@ -142,13 +375,16 @@ async fn metrics() -> impl Responder {
 
 async fn stats_watcher(
     stats: Arc<Mutex<Stats>>,
-    bot: Bot,
+    notifiers: Vec<Box<dyn Notifier>>,
     config_string: String,
-    chat_ids: Vec<String>,
     interval_secs: u64,
+    min_bps: f64,
+    max_lag_seconds: f64,
+    alert_repeat_sec: u64,
 ) {
     let mut prev_blocks_processed_count: u64 = 0;
     let mut prev_state: State = State::Operating;
+    let mut last_alert_sent_at: Option<Instant> = None;
 
     loop {
         tokio::time::sleep(std::time::Duration::from_secs(interval_secs)).await;
@@ -161,70 +397,53 @@ async fn stats_watcher(
         prev_blocks_processed_count = stats_lock.blocks_processed_count;
         drop(stats_lock);
 
-        match prev_state {
-            State::Alerting => {
-                if block_processing_speed > 0.0 {
-                    prev_state = State::Operating;
-                    for chat_id in chat_ids.iter() {
-                        bot.send_message(
-                            chat_id.to_string(),
-                            format!(
-                                "<b>Resolved</b> {}\n BPS is {}",
-                                &config_string, block_processing_speed,
-                            ),
-                        )
-                        // Optional parameters can be supplied by calling setters
-                        .parse_mode(ParseMode::Html)
-                        // To send request to telegram you need to call `.send()` and await the resulting future
-                        .send()
-                        .await
-                        .unwrap();
+        // `LATEST_BLOCK_HEIGHT` and `last_processed_block_height` are both set by the
+        // same `handle_streamer_message` call, so a block-count lag is always ~0 and
+        // can never trip. `CURRENT_LAG_SECONDS` instead reflects how far behind the
+        // block's own timestamp we are, which is independent of local processing.
+        let lag_seconds = CURRENT_LAG_SECONDS.get();
+
+        let condition = if block_processing_speed <= min_bps {
+            Some(AlertCondition::LowBps)
+        } else if lag_seconds > max_lag_seconds {
+            Some(AlertCondition::HighLag)
+        } else {
+            None
+        };
+
+        match condition {
+            Some(condition) => {
+                let should_send = match prev_state {
+                    State::Alerting(_) => last_alert_sent_at
+                        .map(|sent_at| sent_at.elapsed() >= Duration::from_secs(alert_repeat_sec))
+                        .unwrap_or(true),
+                    State::Operating => true,
+                };
+                prev_state = State::Alerting(condition);
+
+                if should_send {
+                    last_alert_sent_at = Some(Instant::now());
+                    let message = format!(
+                        "<b>Alert!</b> {}\n{}\nBPS is {}, lag is {:.1}s",
+                        condition, &config_string, block_processing_speed, lag_seconds
+                    );
+                    for notifier in notifiers.iter() {
+                        notifier.send_alert(&message).await;
                     }
                 }
             }
-            _ => {
-                if block_processing_speed <= 0.0 {
-                    prev_state = State::Alerting;
-                    for chat_id in chat_ids.iter() {
-                        bot.send_message(
-                            chat_id.to_string(),
-                            format!(
-                                "<b>Alert!</b> BPS dropped to {}\n{}",
-                                block_processing_speed, &config_string,
-                            ),
-                        )
-                        // Optional parameters can be supplied by calling setters
-                        .parse_mode(ParseMode::Html)
-                        // To send request to telegram you need to call `.send()` and await the resulting future
-                        .send()
-                        .await
-                        .unwrap();
+            None => {
+                if let State::Alerting(condition) = prev_state {
+                    let message = format!(
+                        "<b>Resolved</b> {}\n{}\nBPS is {}, lag is {:.1}s",
+                        condition, &config_string, block_processing_speed, lag_seconds
+                    );
+                    for notifier in notifiers.iter() {
+                        notifier.send_resolved(&message).await;
                     }
                 }
+                prev_state = State::Operating;
             }
         };
     }
 }
-
-fn init_tracing() {
-    let mut env_filter = EnvFilter::new("near_lake_framework=info");
-
-    if let Ok(rust_log) = std::env::var("RUST_LOG") {
-        if !rust_log.is_empty() {
-            for directive in rust_log.split(',').filter_map(|s| match s.parse() {
-                Ok(directive) => Some(directive),
-                Err(err) => {
-                    eprintln!("Ignoring directive `{}`: {}", s, err);
-                    None
-                }
-            }) {
-                env_filter = env_filter.add_directive(directive);
-            }
-        }
-    }
-
-    tracing_subscriber::fmt::Subscriber::builder()
-        .with_env_filter(env_filter)
-        .with_writer(std::io::stderr)
-        .init();
-}
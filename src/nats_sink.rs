@@ -0,0 +1,90 @@
+use async_nats::jetstream::stream::Config as StreamConfig;
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use crate::{NATS_PUBLISHED, NATS_PUBLISH_ERRORS};
+
+/// What we tell downstream replay consumers about a single processed block.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct BlockSummary {
+    pub height: u64,
+    pub shards: usize,
+    pub chunks: usize,
+    pub transactions: usize,
+    pub receipts: usize,
+    pub timestamp: u64,
+}
+
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Connects to NATS JetStream, binds a stream to `subject` so publishes are
+/// durably stored and consumers can replay from an offset after downtime, and
+/// spawns the publisher task. Returns the sender half of the channel that
+/// feeds it, or `None` if NATS isn't reachable or the stream can't be bound --
+/// a misconfigured or down broker shouldn't take the whole process with it.
+/// The channel is bounded so a slow broker applies backpressure to block
+/// processing instead of buffering summaries without limit or blocking the
+/// `/metrics` endpoint.
+pub(crate) async fn spawn_nats_sink(
+    nats_url: String,
+    subject: String,
+) -> Option<mpsc::Sender<BlockSummary>> {
+    let client = match async_nats::connect(&nats_url).await {
+        Ok(client) => client,
+        Err(err) => {
+            eprintln!(
+                "Failed to connect to NATS at {}: {:?}, continuing without the NATS sink",
+                nats_url, err
+            );
+            return None;
+        }
+    };
+    let jetstream = async_nats::jetstream::new(client);
+
+    let stream_name = subject.replace(['.', '*', '>'], "_");
+    if let Err(err) = jetstream
+        .get_or_create_stream(StreamConfig {
+            name: stream_name,
+            subjects: vec![subject.clone()],
+            ..Default::default()
+        })
+        .await
+    {
+        eprintln!(
+            "Failed to create/bind JetStream stream for subject {}: {:?}, continuing without the NATS sink",
+            subject, err
+        );
+        return None;
+    }
+
+    let (sender, mut receiver) = mpsc::channel::<BlockSummary>(CHANNEL_CAPACITY);
+
+    tokio::spawn(async move {
+        while let Some(summary) = receiver.recv().await {
+            let payload = match serde_json::to_vec(&summary) {
+                Ok(payload) => payload,
+                Err(err) => {
+                    eprintln!("Failed to serialize block summary for NATS: {:?}", err);
+                    NATS_PUBLISH_ERRORS.inc();
+                    continue;
+                }
+            };
+
+            match jetstream.publish(subject.clone(), payload.into()).await {
+                Ok(ack) => match ack.await {
+                    Ok(_) => NATS_PUBLISHED.inc(),
+                    Err(err) => {
+                        eprintln!("NATS publish was not acked: {:?}", err);
+                        NATS_PUBLISH_ERRORS.inc();
+                    }
+                },
+                Err(err) => {
+                    eprintln!("Failed to publish block summary to NATS: {:?}", err);
+                    NATS_PUBLISH_ERRORS.inc();
+                }
+            }
+        }
+    });
+
+    Some(sender)
+}
@@ -0,0 +1,140 @@
+use async_trait::async_trait;
+use teloxide::{
+    payloads::SendMessageSetters,
+    requests::{Request, Requester},
+    types::ParseMode,
+    Bot,
+};
+
+/// Which monitored condition caused a `State::Alerting` transition, so the
+/// eventual resolved message can say what actually recovered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AlertCondition {
+    LowBps,
+    HighLag,
+}
+
+impl std::fmt::Display for AlertCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlertCondition::LowBps => write!(f, "block processing speed below --min-bps"),
+            AlertCondition::HighLag => write!(f, "chain lag above --max-lag-seconds"),
+        }
+    }
+}
+
+/// A backend that can deliver alert/resolved notifications. Implementations are
+/// selected at startup via CLI flags and multiple can be active at once.
+#[async_trait]
+pub(crate) trait Notifier: Send + Sync {
+    async fn send_alert(&self, message: &str);
+    async fn send_resolved(&self, message: &str);
+}
+
+pub(crate) struct TelegramNotifier {
+    bot: Bot,
+    chat_ids: Vec<String>,
+}
+
+impl TelegramNotifier {
+    pub fn new(bot: Bot, chat_ids: Vec<String>) -> Self {
+        Self { bot, chat_ids }
+    }
+
+    async fn send(&self, message: &str) {
+        for chat_id in self.chat_ids.iter() {
+            if let Err(err) = self
+                .bot
+                .send_message(chat_id.to_string(), message.to_string())
+                .parse_mode(ParseMode::Html)
+                .send()
+                .await
+            {
+                eprintln!("Failed to send Telegram alert to {}: {:?}", chat_id, err);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for TelegramNotifier {
+    async fn send_alert(&self, message: &str) {
+        self.send(message).await;
+    }
+
+    async fn send_resolved(&self, message: &str) {
+        self.send(message).await;
+    }
+}
+
+pub(crate) struct SlackNotifier {
+    webhook_url: String,
+    client: reqwest::Client,
+}
+
+impl SlackNotifier {
+    pub fn new(webhook_url: String) -> Self {
+        Self {
+            webhook_url,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn post(&self, message: &str) {
+        let body = serde_json::json!({ "text": message });
+        if let Err(err) = self
+            .client
+            .post(&self.webhook_url)
+            .json(&body)
+            .send()
+            .await
+        {
+            eprintln!("Failed to post Slack alert: {:?}", err);
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    async fn send_alert(&self, message: &str) {
+        self.post(message).await;
+    }
+
+    async fn send_resolved(&self, message: &str) {
+        self.post(message).await;
+    }
+}
+
+/// Posts `{"message": ...}` to an arbitrary URL for operators who don't use
+/// Telegram or Slack but want to wire Pulse alerts into their own tooling.
+pub(crate) struct WebhookNotifier {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn post(&self, message: &str) {
+        let body = serde_json::json!({ "message": message });
+        if let Err(err) = self.client.post(&self.url).json(&body).send().await {
+            eprintln!("Failed to post webhook alert: {:?}", err);
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn send_alert(&self, message: &str) {
+        self.post(message).await;
+    }
+
+    async fn send_resolved(&self, message: &str) {
+        self.post(message).await;
+    }
+}
@@ -0,0 +1,148 @@
+use opentelemetry::{global, metrics::MeterProvider as _};
+use opentelemetry_otlp::WithExportConfig;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+use crate::{
+    BLOCKS_INDEXED, BLOCK_LAG_SECONDS, BLOCK_PROCESS_SECONDS, BPS, CURRENT_LAG_SECONDS,
+    LATEST_BLOCK_HEIGHT, NATS_PUBLISHED, NATS_PUBLISH_ERRORS, STREAM_CONNECTED, STREAM_RECONNECTS,
+};
+
+/// Sets up the stderr `fmt` subscriber used everywhere, optionally layering an
+/// OTLP trace exporter on top so block-processing spans are also shipped to a
+/// collector rather than only ever printed.
+pub(crate) fn init_tracing(otlp_endpoint: Option<&str>) {
+    let mut env_filter = EnvFilter::new("near_lake_framework=info");
+
+    if let Ok(rust_log) = std::env::var("RUST_LOG") {
+        if !rust_log.is_empty() {
+            for directive in rust_log.split(',').filter_map(|s| match s.parse() {
+                Ok(directive) => Some(directive),
+                Err(err) => {
+                    eprintln!("Ignoring directive `{}`: {}", s, err);
+                    None
+                }
+            }) {
+                env_filter = env_filter.add_directive(directive);
+            }
+        }
+    }
+
+    let fmt_layer = tracing_subscriber::fmt::layer().with_writer(std::io::stderr);
+    let registry = tracing_subscriber::registry()
+        .with(env_filter)
+        .with(fmt_layer);
+
+    match otlp_endpoint {
+        Some(endpoint) => {
+            let tracer = opentelemetry_otlp::new_pipeline()
+                .tracing()
+                .with_exporter(
+                    opentelemetry_otlp::new_exporter()
+                        .tonic()
+                        .with_endpoint(endpoint),
+                )
+                .install_batch(opentelemetry::runtime::Tokio)
+                .expect("Failed to install OTLP tracing pipeline");
+
+            registry
+                .with(tracing_opentelemetry::layer().with_tracer(tracer))
+                .init();
+        }
+        None => registry.init(),
+    }
+}
+
+/// Mirrors the existing `pulse_*` Prometheus metrics as OTel observable
+/// instruments, so the same numbers are available over OTLP as well as
+/// `/metrics` without keeping two separate sets of counters in sync by hand.
+/// Gauges and counters are bridged directly; the two histograms are bridged as
+/// their running sum/count (OTel's observable instruments can't replay the
+/// per-observation buckets, but sum/count is enough to derive an average; the
+/// buckets themselves are only available from `/metrics`).
+pub(crate) fn bridge_prometheus_metrics(otlp_endpoint: &str) {
+    let provider = opentelemetry_otlp::new_pipeline()
+        .metrics(opentelemetry::runtime::Tokio)
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(otlp_endpoint),
+        )
+        .build()
+        .expect("Failed to install OTLP metrics pipeline");
+
+    let meter = provider.meter("near-lake-pulse");
+
+    meter
+        .i64_observable_gauge("pulse_latest_block")
+        .with_description("Latest known block height")
+        .with_callback(|observer| observer.observe(LATEST_BLOCK_HEIGHT.get(), &[]))
+        .init();
+
+    meter
+        .u64_observable_counter("pulse_blocks_indexed")
+        .with_description("Number of indexed blocks")
+        .with_callback(|observer| observer.observe(BLOCKS_INDEXED.get(), &[]))
+        .init();
+
+    meter
+        .f64_observable_gauge("pulse_bps")
+        .with_description("Blocks per second")
+        .with_callback(|observer| observer.observe(BPS.get(), &[]))
+        .init();
+
+    meter
+        .u64_observable_counter("pulse_stream_reconnects")
+        .with_description("Number of times the Lake stream has been torn down and rebuilt")
+        .with_callback(|observer| observer.observe(STREAM_RECONNECTS.get(), &[]))
+        .init();
+
+    meter
+        .i64_observable_gauge("pulse_stream_connected")
+        .with_description("Whether the Lake stream is currently connected (1) or being rebuilt (0)")
+        .with_callback(|observer| observer.observe(STREAM_CONNECTED.get(), &[]))
+        .init();
+
+    meter
+        .u64_observable_counter("pulse_nats_published")
+        .with_description("Number of block summaries published to NATS")
+        .with_callback(|observer| observer.observe(NATS_PUBLISHED.get(), &[]))
+        .init();
+
+    meter
+        .u64_observable_counter("pulse_nats_publish_errors")
+        .with_description("Number of failed NATS publish attempts")
+        .with_callback(|observer| observer.observe(NATS_PUBLISH_ERRORS.get(), &[]))
+        .init();
+
+    meter
+        .f64_observable_gauge("pulse_current_lag_seconds")
+        .with_description("Wall-clock lag behind the most recently processed block's timestamp")
+        .with_callback(|observer| observer.observe(CURRENT_LAG_SECONDS.get(), &[]))
+        .init();
+
+    meter
+        .f64_observable_counter("pulse_block_process_seconds_sum")
+        .with_description("Running sum of wall-clock time spent inside handle_streamer_message")
+        .with_callback(|observer| observer.observe(BLOCK_PROCESS_SECONDS.get_sample_sum(), &[]))
+        .init();
+    meter
+        .u64_observable_counter("pulse_block_process_seconds_count")
+        .with_description("Number of handle_streamer_message observations")
+        .with_callback(|observer| observer.observe(BLOCK_PROCESS_SECONDS.get_sample_count(), &[]))
+        .init();
+
+    meter
+        .f64_observable_counter("pulse_block_lag_seconds_sum")
+        .with_description("Running sum of observed chain-lag seconds")
+        .with_callback(|observer| observer.observe(BLOCK_LAG_SECONDS.get_sample_sum(), &[]))
+        .init();
+    meter
+        .u64_observable_counter("pulse_block_lag_seconds_count")
+        .with_description("Number of chain-lag observations")
+        .with_callback(|observer| observer.observe(BLOCK_LAG_SECONDS.get_sample_count(), &[]))
+        .init();
+
+    global::set_meter_provider(provider);
+}